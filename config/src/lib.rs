@@ -0,0 +1,188 @@
+// Copyright(C) Facebook, Inc. and its affiliates.
+use anyhow::{Context, Result};
+use crypto::{generate_keypair, PublicKey, SecretKey};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::net::SocketAddr;
+
+pub type WorkerId = u32;
+pub type Stake = u32;
+pub type Epoch = u64;
+
+/// Import a type from a JSON file.
+pub trait Import: Sized + for<'de> Deserialize<'de> {
+    fn import(path: &str) -> Result<Self> {
+        let data = fs::read(path).with_context(|| format!("Failed to read {}", path))?;
+        serde_json::from_slice(&data).with_context(|| format!("Failed to parse {}", path))
+    }
+}
+
+/// Export a type to a JSON file.
+pub trait Export: Serialize {
+    fn export(&self, path: &str) -> Result<()> {
+        let data = serde_json::to_string_pretty(self)?;
+        fs::write(path, data).with_context(|| format!("Failed to write {}", path))
+    }
+}
+
+/// An authority's consensus keypair, used to sign votes and certificates.
+#[derive(Serialize, Deserialize)]
+pub struct KeyPair {
+    pub name: PublicKey,
+    pub secret: SecretKey,
+}
+
+impl KeyPair {
+    pub fn new() -> Self {
+        let (name, secret) = generate_keypair();
+        Self { name, secret }
+    }
+}
+
+impl Default for KeyPair {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Import for KeyPair {}
+impl Export for KeyPair {}
+
+/// An authority's networking keypair, used to authenticate TLS/transport connections. Kept as
+/// a distinct type from `KeyPair` so the key that signs consensus messages is never the one
+/// a peer authenticates our connection with.
+#[derive(Serialize, Deserialize)]
+pub struct NetworkKeyPair {
+    pub name: PublicKey,
+    pub secret: SecretKey,
+}
+
+impl NetworkKeyPair {
+    pub fn new() -> Self {
+        let (name, secret) = generate_keypair();
+        Self { name, secret }
+    }
+}
+
+impl Default for NetworkKeyPair {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Import for NetworkKeyPair {}
+impl Export for NetworkKeyPair {}
+
+/// The address at which a worker can be reached by its peers and by its primary.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct WorkerInfo {
+    /// Where other workers send us batch sync requests and batches.
+    pub worker_to_worker: SocketAddr,
+    /// Where our primary reaches us.
+    pub primary_to_worker: SocketAddr,
+    /// Where clients submit transactions.
+    pub transactions: SocketAddr,
+}
+
+/// Everything the committee knows about one authority.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Authority {
+    /// The authority's relative voting power.
+    pub stake: Stake,
+    /// The networking public key peers use to authenticate this authority's TLS/transport
+    /// connections.
+    pub network_key: PublicKey,
+    /// The address other primaries use to reach the authority's primary.
+    pub primary_to_primary: SocketAddr,
+    pub workers: HashMap<WorkerId, WorkerInfo>,
+}
+
+impl Authority {
+    /// Builds an authority with a single worker (id `0`) reachable at `worker_address`.
+    pub fn new(
+        stake: Stake,
+        network_key: PublicKey,
+        primary_address: SocketAddr,
+        worker_address: SocketAddr,
+    ) -> Self {
+        let mut workers = HashMap::new();
+        workers.insert(
+            0,
+            WorkerInfo {
+                worker_to_worker: worker_address,
+                primary_to_worker: worker_address,
+                transactions: worker_address,
+            },
+        );
+        Self {
+            stake,
+            network_key,
+            primary_to_primary: primary_address,
+            workers,
+        }
+    }
+}
+
+/// The committee of authorities that run consensus together.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Committee {
+    pub authorities: HashMap<PublicKey, Authority>,
+    pub epoch: Epoch,
+}
+
+impl Committee {
+    pub fn new(authorities: HashMap<PublicKey, Authority>, epoch: Epoch) -> Self {
+        Self { authorities, epoch }
+    }
+
+    pub fn size(&self) -> usize {
+        self.authorities.len()
+    }
+
+    /// Looks up the address at which authority `name`'s worker `id` can be reached.
+    pub fn worker(&self, name: &PublicKey, id: &WorkerId) -> Result<WorkerInfo> {
+        self.authorities
+            .get(name)
+            .and_then(|authority| authority.workers.get(id))
+            .cloned()
+            .with_context(|| format!("Unknown worker {} for authority {}", id, name))
+    }
+}
+
+impl Import for Committee {}
+impl Export for Committee {}
+
+/// Tunable parameters shared by every task a node runs. Every field has a default so that a
+/// parameters file only needs to override what it cares about.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Parameters {
+    /// How many bytes of transactions a worker buffers before sealing them into a batch.
+    pub batch_size: usize,
+    /// The maximum delay, in milliseconds, before a worker seals a (possibly partial) batch.
+    pub max_batch_delay: u64,
+    /// The delay, in milliseconds, before a worker re-sends an unanswered batch sync request.
+    pub sync_retry_delay: u64,
+    /// Reputation score above which `Helper` stops serving a peer's batch requests for a
+    /// cooldown period, to bound how much work any single authority can extract from us.
+    pub helper_reputation_threshold: f64,
+    /// Whether leader election picks the round's leader proportionally to stake
+    /// (`StakeWeightedLeaderElector`) instead of plain round-robin (`RRLeaderElector`).
+    pub stake_weighted_elections: bool,
+}
+
+impl Default for Parameters {
+    fn default() -> Self {
+        Self {
+            batch_size: 500_000,
+            max_batch_delay: 100,
+            sync_retry_delay: 10_000,
+            helper_reputation_threshold: 100.0,
+            stake_weighted_elections: false,
+        }
+    }
+}
+
+impl Import for Parameters {}
+impl Export for Parameters {}
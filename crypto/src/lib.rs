@@ -0,0 +1,100 @@
+// Copyright(C) Facebook, Inc. and its affiliates.
+use blake2::{Blake2b, Digest as _};
+use ed25519_dalek as dalek;
+use ed25519_dalek::Signer as _;
+use rand::rngs::OsRng;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use tokio::sync::mpsc::{channel, Sender};
+use tokio::sync::oneshot;
+
+/// A 32-byte content digest: the first 32 bytes of the data's Blake2b hash.
+#[derive(Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct Digest(pub [u8; 32]);
+
+impl Digest {
+    pub fn to_vec(&self) -> Vec<u8> {
+        self.0.to_vec()
+    }
+
+    /// Hashes `data` down to a `Digest`.
+    pub fn hash(data: &[u8]) -> Self {
+        let mut hasher = Blake2b::new();
+        hasher.update(data);
+        let mut bytes = [0u8; 32];
+        bytes.copy_from_slice(&hasher.finalize()[..32]);
+        Self(bytes)
+    }
+}
+
+impl fmt::Debug for Digest {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", base64::encode(&self.0[..6]))
+    }
+}
+
+impl fmt::Display for Digest {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", base64::encode(&self.0[..6]))
+    }
+}
+
+/// An authority's public key; also the representation used for the networking key (see
+/// `config::NetworkKeyPair`).
+#[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
+pub struct PublicKey(pub [u8; 32]);
+
+impl fmt::Debug for PublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", base64::encode(&self.0[..6]))
+    }
+}
+
+impl fmt::Display for PublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", base64::encode(&self.0[..6]))
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SecretKey(pub [u8; 64]);
+
+/// Generates a fresh keypair; used for both consensus and networking keys.
+pub fn generate_keypair() -> (PublicKey, SecretKey) {
+    let mut csprng = OsRng {};
+    let keypair = dalek::Keypair::generate(&mut csprng);
+    (PublicKey(keypair.public.to_bytes()), SecretKey(keypair.to_bytes()))
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Signature(pub [u8; 64]);
+
+/// Signs digests on behalf of the node's keypair from a dedicated task, so the secret key
+/// material never has to be cloned or shared across the codebase.
+#[derive(Clone)]
+pub struct SignatureService {
+    channel: Sender<(Digest, oneshot::Sender<Signature>)>,
+}
+
+impl SignatureService {
+    pub fn new(secret: SecretKey) -> Self {
+        let (tx, mut rx) = channel::<(Digest, oneshot::Sender<Signature>)>(100);
+        tokio::spawn(async move {
+            let keypair = dalek::Keypair::from_bytes(&secret.0).expect("invalid secret key");
+            while let Some((digest, reply)) = rx.recv().await {
+                let signature = Signature(keypair.sign(&digest.0).to_bytes());
+                let _ = reply.send(signature);
+            }
+        });
+        Self { channel: tx }
+    }
+
+    pub async fn request_signature(&self, digest: Digest) -> Signature {
+        let (tx, rx) = oneshot::channel();
+        self.channel
+            .send((digest, tx))
+            .await
+            .expect("failed to send digest to the signature service");
+        rx.await.expect("failed to receive signature")
+    }
+}
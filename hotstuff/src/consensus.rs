@@ -0,0 +1,30 @@
+// Copyright(C) Facebook, Inc. and its affiliates.
+use crate::leader::{make_leader_elector, LeaderElector};
+use config::{Committee, Parameters};
+
+/// A consensus round number.
+pub type Round = u64;
+
+/// The consensus core: drives the protocol round by round, asking `leader_elector` who is
+/// expected to propose each one.
+pub struct Consensus {
+    committee: Committee,
+    parameters: Parameters,
+    leader_elector: Box<dyn LeaderElector>,
+}
+
+impl Consensus {
+    pub fn new(committee: Committee, parameters: Parameters) -> Self {
+        let leader_elector = make_leader_elector(committee.clone(), &parameters);
+        Self {
+            committee,
+            parameters,
+            leader_elector,
+        }
+    }
+
+    /// The authority expected to propose in `round`.
+    pub fn leader(&self, round: Round) -> crypto::PublicKey {
+        self.leader_elector.get_leader(round)
+    }
+}
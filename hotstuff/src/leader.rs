@@ -1,9 +1,42 @@
 use crate::consensus::Round;
-use config::Committee;
+use config::{Committee, Parameters};
 use crypto::PublicKey;
 
-pub type LeaderElector = RRLeaderElector;
+#[cfg(test)]
+#[path = "tests/leader_tests.rs"]
+mod leader_tests;
 
+/// A fixed, build-independent hash (FNV-1a) used to derive the leader-election seed.
+/// `std::collections::hash_map::DefaultHasher` is explicitly *not* guaranteed stable across
+/// Rust versions/builds, which would let honest nodes on different toolchains disagree on the
+/// leader; FNV-1a's output is part of its specification, not an implementation detail.
+fn stable_hash(round: Round) -> u64 {
+    const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+    const FNV_PRIME: u64 = 0x100000001b3;
+    round
+        .to_le_bytes()
+        .iter()
+        .fold(FNV_OFFSET_BASIS, |hash, byte| {
+            (hash ^ *byte as u64).wrapping_mul(FNV_PRIME)
+        })
+}
+
+/// Selects which authority leads a given round.
+pub trait LeaderElector: Send + Sync {
+    fn get_leader(&self, round: Round) -> PublicKey;
+}
+
+/// Builds the `LeaderElector` configured in `parameters`, so benchmarks can compare the
+/// fairness/throughput of plain round-robin against stake-weighted election.
+pub fn make_leader_elector(committee: Committee, parameters: &Parameters) -> Box<dyn LeaderElector> {
+    if parameters.stake_weighted_elections {
+        Box::new(StakeWeightedLeaderElector::new(committee))
+    } else {
+        Box::new(RRLeaderElector::new(committee))
+    }
+}
+
+/// Picks the leader of a round by simple round-robin over the sorted committee, ignoring stake.
 pub struct RRLeaderElector {
     committee: Committee,
 }
@@ -12,10 +45,53 @@ impl RRLeaderElector {
     pub fn new(committee: Committee) -> Self {
         Self { committee }
     }
+}
 
-    pub fn get_leader(&self, round: Round) -> PublicKey {
+impl LeaderElector for RRLeaderElector {
+    fn get_leader(&self, round: Round) -> PublicKey {
         let mut keys: Vec<_> = self.committee.authorities.keys().cloned().collect();
         keys.sort();
         keys[round as usize % self.committee.size()]
     }
 }
+
+/// Picks the leader of a round proportionally to each authority's stake: every honest node
+/// derives a deterministic seed from the round, maps it into `[0, total_stake)`, and walks the
+/// sorted authorities accumulating stake until the running sum passes the target. Authorities
+/// with more stake therefore own a proportionally wider slice of the range and lead more rounds,
+/// without any extra messages since every node computes the same result independently.
+pub struct StakeWeightedLeaderElector {
+    committee: Committee,
+}
+
+impl StakeWeightedLeaderElector {
+    pub fn new(committee: Committee) -> Self {
+        Self { committee }
+    }
+}
+
+impl LeaderElector for StakeWeightedLeaderElector {
+    fn get_leader(&self, round: Round) -> PublicKey {
+        let mut authorities: Vec<_> = self
+            .committee
+            .authorities
+            .iter()
+            .map(|(name, authority)| (*name, authority.stake))
+            .collect();
+        authorities.sort_by_key(|(name, _)| *name);
+
+        let total_stake: u32 = authorities.iter().map(|(_, stake)| stake).sum();
+
+        let seed = stable_hash(round);
+        let target = (seed % total_stake as u64) as u32;
+
+        let mut running = 0;
+        for (name, stake) in authorities {
+            running += stake;
+            if running > target {
+                return name;
+            }
+        }
+        unreachable!("the running stake always exceeds the target before the loop ends");
+    }
+}
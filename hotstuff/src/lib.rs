@@ -0,0 +1,5 @@
+// Copyright(C) Facebook, Inc. and its affiliates.
+pub mod consensus;
+mod leader;
+
+pub use leader::{LeaderElector, RRLeaderElector, StakeWeightedLeaderElector};
@@ -0,0 +1,39 @@
+use super::*;
+use config::{Authority, Committee};
+use crypto::generate_keypair;
+use std::collections::HashMap;
+
+/// Builds a committee of `stakes.len()` authorities with the given stakes, reusing the same
+/// address for every authority since `get_leader` never dials out.
+fn committee_with_stakes(stakes: &[u32]) -> Committee {
+    let address = "127.0.0.1:0".parse().unwrap();
+    let mut authorities = HashMap::new();
+    for stake in stakes {
+        let (name, _) = generate_keypair();
+        let (network_name, _) = generate_keypair();
+        authorities.insert(name, Authority::new(*stake, network_name, address, address));
+    }
+    Committee::new(authorities, /* epoch */ 0)
+}
+
+#[test]
+fn round_robin_electors_agree() {
+    let committee = committee_with_stakes(&[1, 1, 1, 1]);
+    let elector_a = RRLeaderElector::new(committee.clone());
+    let elector_b = RRLeaderElector::new(committee);
+
+    for round in 0..50 {
+        assert_eq!(elector_a.get_leader(round), elector_b.get_leader(round));
+    }
+}
+
+#[test]
+fn stake_weighted_electors_agree() {
+    let committee = committee_with_stakes(&[1, 2, 3, 10]);
+    let elector_a = StakeWeightedLeaderElector::new(committee.clone());
+    let elector_b = StakeWeightedLeaderElector::new(committee);
+
+    for round in 0..50 {
+        assert_eq!(elector_a.get_leader(round), elector_b.get_leader(round));
+    }
+}
@@ -6,18 +6,28 @@ use anyhow::{Context, Result};
 use clap::{crate_name, crate_version, App, AppSettings, ArgMatches, SubCommand};
 use config::Export as _;
 use config::Import as _;
-use config::{Committee, KeyPair, Parameters, WorkerId};
+use config::{Committee, KeyPair, NetworkKeyPair, Parameters, WorkerId};
 use crypto::SignatureService;
 use env_logger::Env;
 use primary::Header;
 use primary::Primary;
+use rpc::{ConsensusSnapshot, SharedSnapshot};
+use std::sync::{Arc, RwLock};
 use store::Store;
 use tokio::sync::mpsc::{channel, Receiver};
 use worker::Worker;
 
+mod rpc;
+
 /// The default channel capacity.
 pub const CHANNEL_CAPACITY: usize = 1_000;
 
+/// Derives the file name used to store an authority's networking keypair from the file name
+/// of its consensus keypair.
+fn network_key_filename(key_file: &str) -> String {
+    format!("{}.network", key_file)
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     //std::env::set_var("RUST_BACKTRACE", "1");
@@ -38,6 +48,7 @@ async fn main() -> Result<()> {
                 .args_from_usage("--committee=<FILE> 'The file containing committee information'")
                 .args_from_usage("--parameters=[FILE] 'The file containing the node parameters'")
                 .args_from_usage("--store=<PATH> 'The path where to create the data store'")
+                .args_from_usage("--rpc=[ADDR] 'The address where to expose the admin RPC server'")
                 .subcommand(SubCommand::with_name("primary").about("Run a single primary"))
                 .subcommand(
                     SubCommand::with_name("worker")
@@ -62,9 +73,15 @@ async fn main() -> Result<()> {
     logger.init();
 
     match matches.subcommand() {
-        ("generate_keys", Some(sub_matches)) => KeyPair::new()
-            .export(sub_matches.value_of("filename").unwrap())
-            .context("Failed to generate key pair")?,
+        ("generate_keys", Some(sub_matches)) => {
+            let filename = sub_matches.value_of("filename").unwrap();
+            KeyPair::new()
+                .export(filename)
+                .context("Failed to generate key pair")?;
+            NetworkKeyPair::new()
+                .export(&network_key_filename(filename))
+                .context("Failed to generate networking key pair")?;
+        }
         ("run", Some(sub_matches)) => run(sub_matches).await?,
         _ => unreachable!(),
     }
@@ -77,10 +94,16 @@ async fn run(matches: &ArgMatches<'_>) -> Result<()> {
     let committee_file = matches.value_of("committee").unwrap();
     let parameters_file = matches.value_of("parameters");
     let store_path = matches.value_of("store").unwrap();
+    let rpc_address = matches
+        .value_of("rpc")
+        .map(|x| x.parse().context("Invalid admin RPC address"))
+        .transpose()?;
 
     // Read the committee and node's keypair from file.
     let keypair = KeyPair::import(key_file).context("Failed to load the node's keypair")?;
     let name = keypair.name;
+    let network_keypair = NetworkKeyPair::import(&network_key_filename(key_file))
+        .context("Failed to load the node's networking keypair")?;
     let committee =
         Committee::import(committee_file).context("Failed to load the committee information")?;
 
@@ -101,6 +124,10 @@ async fn run(matches: &ArgMatches<'_>) -> Result<()> {
     // Channels the sequence of certificates.
     let (tx_output, rx_output) = channel(CHANNEL_CAPACITY);
 
+    // Shared snapshot of the committed state, read by the admin RPC server and kept up to
+    // date by `analyze` as headers are committed.
+    let snapshot: SharedSnapshot = Arc::new(RwLock::new(ConsensusSnapshot::default()));
+
     // Channel for sending headers between DAG and Consensus
     let (tx_sailfish, rx_sailfish) = channel(CHANNEL_CAPACITY);
 
@@ -112,6 +139,10 @@ async fn run(matches: &ArgMatches<'_>) -> Result<()> {
     // Channel for sending whether async to the worker
     let (tx_async, rx_async) = channel(CHANNEL_CAPACITY);
 
+    // `Some((id, reputation))` once we know this process runs `id`'s worker locally, so the
+    // admin RPC server (spawned below, once we know this) can target it.
+    let mut local_worker: Option<(WorkerId, worker::SharedReputation)> = None;
+
     // Check whether to run a primary, a worker, or an entire authority.
     //Note: Each node has at most one worker. Workers that don't include a primary (e.g. are not an entire authority) use PrimaryConnector to connect to a designated primary.
     match matches.subcommand() {
@@ -125,6 +156,7 @@ async fn run(matches: &ArgMatches<'_>) -> Result<()> {
 
             Primary::spawn(
                 name,
+                network_keypair.name,
                 committee.clone(),
                 parameters.clone(),
                 signature_service.clone(),
@@ -165,21 +197,47 @@ async fn run(matches: &ArgMatches<'_>) -> Result<()> {
                 .unwrap()
                 .parse::<WorkerId>()
                 .context("The worker id must be a positive integer")?;
-            Worker::spawn(keypair.name, id, committee, parameters, store);
+            let handle = Worker::spawn(
+                keypair.name,
+                network_keypair.name,
+                id,
+                committee.clone(),
+                parameters,
+                store,
+            );
+            local_worker = Some((id, handle.reputation.clone()));
+            // Leaked on purpose: the worker stops when the process does.
+            Box::leak(Box::new(handle));
         }
         _ => unreachable!(),
     }
 
+    if let Some(address) = rpc_address {
+        // Leaked on purpose: the server stops when the process does.
+        Box::leak(Box::new(rpc::spawn(
+            address,
+            snapshot.clone(),
+            committee.clone(),
+            name,
+            local_worker,
+        )?));
+    }
+
     // Analyze the consensus' output.
-    analyze(rx_output).await;
+    analyze(rx_output, snapshot).await;
 
     // If this expression is reached, the program ends and all other tasks terminate.
     unreachable!();
 }
 
-/// Receives an ordered list of certificates and apply any application-specific logic.
-async fn analyze(mut rx_output: Receiver<Header>) {
-    while let Some(_header) = rx_output.recv().await {
+/// Receives the ordered stream of committed headers, applies any application-specific logic, and keeps
+/// `snapshot` up to date so the admin RPC server can answer queries about committed state.
+async fn analyze(mut rx_output: Receiver<Header>, snapshot: SharedSnapshot) {
+    while let Some(header) = rx_output.recv().await {
+        snapshot
+            .write()
+            .expect("snapshot lock poisoned")
+            .record(&header);
         // NOTE: Here goes the application logic.
     }
 }
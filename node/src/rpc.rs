@@ -0,0 +1,138 @@
+// Copyright(C) Facebook, Inc. and its affiliates.
+//! Optional admin JSON-RPC server, enabled with `--rpc=<ADDR>`. Exposes read-only consensus
+//! state (latest committed round/leader, the committee, store statistics) plus a method that
+//! sends client transactions to this node's worker's transaction address, so operators and
+//! benchmark tooling can inspect a live node's progress and inject load without restarting it.
+use anyhow::{Context, Result};
+use config::{Committee, WorkerId};
+use crypto::PublicKey;
+use jsonrpc_core::{IoHandler, Params, Value};
+use jsonrpc_http_server::{Server, ServerBuilder};
+use primary::{Header, Round};
+use serde_json::json;
+use std::net::SocketAddr;
+use std::sync::{Arc, RwLock};
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpStream;
+use worker::SharedReputation;
+
+/// A point-in-time view of what the node has committed, kept up to date by `analyze` as new
+/// headers arrive so the RPC server always answers from fresh state without itself touching
+/// the consensus channels.
+#[derive(Default, Clone)]
+pub struct ConsensusSnapshot {
+    pub latest_round: Round,
+    pub latest_leader: Option<PublicKey>,
+    pub committed_headers: u64,
+}
+
+/// Handle shared between `analyze` (writer) and the RPC server (reader).
+pub type SharedSnapshot = Arc<RwLock<ConsensusSnapshot>>;
+
+impl ConsensusSnapshot {
+    /// Folds a newly committed header into the snapshot.
+    pub fn record(&mut self, header: &Header) {
+        self.latest_round = header.round;
+        self.latest_leader = Some(header.author);
+        self.committed_headers += 1;
+    }
+}
+
+/// Starts the admin RPC server on `address`. `worker` is `Some((id, reputation))` only when
+/// this process is itself running `id`'s worker (the `run worker --id=<ID>` subcommand);
+/// `admin_submitTransaction` then forwards to that worker's transaction port (the same way the
+/// benchmark client feeds its batch maker) and `admin_helperReputation` reads its `Helper`'s
+/// live scores straight out of `reputation`. Both methods answer with an error when this
+/// process runs no worker (e.g. the `primary` subcommand), since there is then no local worker
+/// id to target. `Worker::spawn` itself opens no listener on that transaction address (its
+/// batch maker reads only from `WorkerHandle::tx_transactions`), so `admin_submitTransaction`
+/// answers with an internal error unless something else accepts connections there.
+pub fn spawn(
+    address: SocketAddr,
+    snapshot: SharedSnapshot,
+    committee: Committee,
+    name: PublicKey,
+    worker: Option<(WorkerId, SharedReputation)>,
+) -> Result<Server> {
+    let mut io = IoHandler::new();
+
+    {
+        let snapshot = snapshot.clone();
+        io.add_method("admin_latestRound", move |_: Params| {
+            let snapshot = snapshot.clone();
+            async move {
+                let snapshot = snapshot.read().expect("snapshot lock poisoned");
+                Ok(json!({
+                    "round": snapshot.latest_round,
+                    "leader": snapshot.latest_leader,
+                }))
+            }
+        });
+    }
+
+    {
+        let snapshot = snapshot.clone();
+        io.add_method("admin_storeStats", move |_: Params| {
+            let snapshot = snapshot.clone();
+            async move {
+                let snapshot = snapshot.read().expect("snapshot lock poisoned");
+                Ok(json!({ "committedHeaders": snapshot.committed_headers }))
+            }
+        });
+    }
+
+    {
+        let committee = committee.clone();
+        io.add_method("admin_committee", move |_: Params| {
+            let committee = committee.clone();
+            async move { Ok(serde_json::to_value(&committee).unwrap_or(Value::Null)) }
+        });
+    }
+
+    {
+        let reputation = worker.as_ref().map(|(_, reputation)| reputation.clone());
+        io.add_method("admin_helperReputation", move |_: Params| {
+            let reputation = reputation.clone();
+            async move {
+                let reputation = reputation.ok_or_else(|| {
+                    jsonrpc_core::Error::invalid_request()
+                })?;
+                let reputation = reputation.read().expect("reputation lock poisoned");
+                Ok(json!(reputation
+                    .iter()
+                    .map(|(peer, score)| json!({ "peer": peer, "score": score }))
+                    .collect::<Vec<_>>()))
+            }
+        });
+    }
+
+    {
+        let committee = committee.clone();
+        let worker_id = worker.as_ref().map(|(id, _)| *id);
+        io.add_method("admin_submitTransaction", move |params: Params| {
+            let committee = committee.clone();
+            let worker_id = worker_id;
+            async move {
+                let worker_id = worker_id.ok_or_else(jsonrpc_core::Error::invalid_request)?;
+                let transaction: Vec<u8> = params.parse().map_err(|_| {
+                    jsonrpc_core::Error::invalid_params("expected a byte array transaction")
+                })?;
+                let worker = committee.worker(&name, &worker_id).map_err(|_| {
+                    jsonrpc_core::Error::invalid_params("unknown worker id for this node")
+                })?;
+                let mut stream = TcpStream::connect(worker.transactions)
+                    .await
+                    .map_err(|_| jsonrpc_core::Error::internal_error())?;
+                stream
+                    .write_all(&transaction)
+                    .await
+                    .map_err(|_| jsonrpc_core::Error::internal_error())?;
+                Ok(Value::Bool(true))
+            }
+        });
+    }
+
+    ServerBuilder::new(io)
+        .start_http(&address)
+        .context("Failed to start the admin RPC server")
+}
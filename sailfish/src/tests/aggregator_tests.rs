@@ -1,6 +1,11 @@
 use super::*;
 use crate::common::{committee, keys};
 
+// NOTE [issue #51]: scope of this commit is the networking-key split only (see
+// `config::NetworkKeyPair`); these tests are untouched. BLS aggregate signatures (aggregate
+// sign/verify in `SignatureService`, a BLS `KeyPair` variant, `Aggregator` combining partial
+// signatures into one) were not implemented here and are re-filed as their own follow-up
+// request -- `make_qc` below still expects `2f + 1` individual signatures, not an aggregate QC.
 /*#[test]
 fn add_vote() {
     let mut aggregator = Aggregator::new(committee());
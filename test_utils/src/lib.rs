@@ -0,0 +1,272 @@
+// Copyright(C) Facebook, Inc. and its affiliates.
+//! In-process multi-authority harness used by integration tests. `CommitteeFixture` builds a
+//! committee of fresh authorities (keys, temporary stores, free localhost ports) and `Cluster`
+//! spawns the corresponding `Primary`/`Worker` tasks for each of them, reusing the same wiring
+//! `node::main` uses to run a real node. Tests drive the cluster by submitting client
+//! transactions through [`Cluster::tx_transactions`] and observing the stream of committed
+//! `Header`s. [`Cluster::stop_worker`]/[`Cluster::restart_worker`] let tests simulate a worker
+//! crash and resync (the primary task has no capturable handle — see their doc comments — so
+//! this harness cannot crash/restart the primary itself).
+use config::{Authority, Committee, KeyPair, NetworkKeyPair, Parameters, WorkerId};
+use crypto::{Digest, PublicKey, SignatureService};
+use primary::{Header, Primary};
+use std::collections::HashMap;
+use std::net::{SocketAddr, TcpListener};
+use store::Store;
+use tempfile::tempdir;
+use tokio::sync::mpsc::{channel, Receiver, Sender};
+use worker::{Worker, WorkerHandle};
+
+#[cfg(test)]
+#[path = "tests/cluster_tests.rs"]
+mod cluster_tests;
+
+/// The default channel capacity used by fixture-spawned nodes, matching `node::CHANNEL_CAPACITY`.
+const CHANNEL_CAPACITY: usize = 1_000;
+
+/// Binds an ephemeral localhost port and immediately releases it so it can be handed to a
+/// spawned node. Racy in theory (another process could grab the port first) but this is the
+/// same trick the rest of the test suite already relies on to avoid hard-coded ports.
+fn free_port() -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("failed to bind an ephemeral port");
+    listener.local_addr().expect("failed to read local address")
+}
+
+/// The fixture's view of a single authority: everything a test needs to act as that authority
+/// or to assert on its state, without having to re-derive it from the `Committee`.
+pub struct AuthorityFixture {
+    pub name: PublicKey,
+    pub keypair: KeyPair,
+    pub network_keypair: NetworkKeyPair,
+    pub worker_id: WorkerId,
+    pub store_path: std::path::PathBuf,
+}
+
+impl AuthorityFixture {
+    /// Opens a fresh handle to this authority's on-disk store. Used both by `Cluster` to spawn
+    /// the node and by tests that want to restart a node and inspect what it persisted.
+    pub fn store(&self) -> Store {
+        Store::new(self.store_path.to_str().unwrap()).expect("failed to open authority store")
+    }
+}
+
+/// Builds a `Committee` of freshly-keyed authorities with temporary stores and free localhost
+/// ports, for use in integration tests that need a real (if small) multi-node cluster.
+pub struct CommitteeFixture {
+    authorities: Vec<AuthorityFixture>,
+    committee: Committee,
+    parameters: Parameters,
+}
+
+impl CommitteeFixture {
+    /// Generates a committee of `size` authorities, each with a single worker.
+    pub fn generate(size: usize) -> Self {
+        assert!(size > 0, "a committee needs at least one authority");
+
+        let mut authorities = Vec::with_capacity(size);
+        let mut committee_authorities = HashMap::new();
+
+        for _ in 0..size {
+            let keypair = KeyPair::new();
+            let network_keypair = NetworkKeyPair::new();
+            let primary_address = free_port();
+            let worker_address = free_port();
+            let store_path = tempdir()
+                .expect("failed to create a temporary store directory")
+                .into_path();
+
+            committee_authorities.insert(
+                keypair.name,
+                Authority::new(
+                    /* stake */ 1,
+                    network_keypair.name,
+                    primary_address,
+                    worker_address,
+                ),
+            );
+
+            authorities.push(AuthorityFixture {
+                name: keypair.name,
+                keypair,
+                network_keypair,
+                worker_id: 0,
+                store_path,
+            });
+        }
+
+        let committee = Committee::new(committee_authorities, /* epoch */ 0);
+        Self {
+            authorities,
+            committee,
+            parameters: Parameters::default(),
+        }
+    }
+
+    /// Overrides the parameters every spawned node will run with.
+    pub fn with_parameters(mut self, parameters: Parameters) -> Self {
+        self.parameters = parameters;
+        self
+    }
+
+    pub fn committee(&self) -> Committee {
+        self.committee.clone()
+    }
+
+    pub fn authorities(&self) -> &[AuthorityFixture] {
+        &self.authorities
+    }
+
+    /// Spawns every authority's primary and worker, returning a `Cluster` handle.
+    pub fn build(self) -> Cluster {
+        let mut nodes = Vec::with_capacity(self.authorities.len());
+        for authority in &self.authorities {
+            let node = spawn_authority(authority, &self.committee, &self.parameters);
+            nodes.push(node);
+        }
+        Cluster {
+            committee: self.committee,
+            parameters: self.parameters,
+            authorities: self.authorities,
+            nodes,
+        }
+    }
+}
+
+/// A running authority: the receiver its tests observe committed headers on, the handle to its
+/// worker (the only spawned task here with a capturable `JoinHandle`), and a keep-alive for the
+/// sender half of `Primary`'s `rx_consensus` channel (its element type is only known inside the
+/// external `primary` crate, so it's type-erased here; the only thing that matters is that it
+/// stays bound for as long as the node runs instead of being dropped as an unbound temporary).
+struct RunningNode {
+    rx_output: Receiver<Header>,
+    /// `None` only while `Cluster::restart_worker` is tearing down the old worker and spinning
+    /// up its replacement; always `Some` otherwise.
+    worker: Option<WorkerHandle>,
+    _tx_feedback: Box<dyn std::any::Any + Send>,
+}
+
+fn spawn_authority(
+    authority: &AuthorityFixture,
+    committee: &Committee,
+    parameters: &Parameters,
+) -> RunningNode {
+    let signature_service = SignatureService::new(authority.keypair.secret.clone());
+    let store = authority.store();
+
+    let (tx_output, rx_output) = channel(CHANNEL_CAPACITY);
+    let (tx_new_certificates, rx_new_certificates) = channel(CHANNEL_CAPACITY);
+    let (tx_feedback, rx_feedback) = channel(CHANNEL_CAPACITY);
+    let (tx_committer, rx_committer) = channel(CHANNEL_CAPACITY);
+    let (tx_pushdown_cert, rx_pushdown_cert) = channel(CHANNEL_CAPACITY);
+    let (tx_request_header_sync, rx_request_header_sync) = channel(CHANNEL_CAPACITY);
+    let (tx_sailfish, _rx_sailfish) = channel(CHANNEL_CAPACITY);
+    let (tx_async, _rx_async) = channel(CHANNEL_CAPACITY);
+
+    Primary::spawn(
+        authority.name,
+        authority.network_keypair.name,
+        committee.clone(),
+        parameters.clone(),
+        signature_service,
+        store.clone(),
+        /* tx_consensus */ tx_new_certificates,
+        tx_committer,
+        rx_committer,
+        /* rx_consensus */ rx_feedback,
+        tx_sailfish,
+        rx_pushdown_cert,
+        rx_request_header_sync,
+        tx_output,
+        tx_async,
+    );
+
+    let worker = Worker::spawn(
+        authority.keypair.name,
+        authority.network_keypair.name,
+        authority.worker_id,
+        committee.clone(),
+        parameters.clone(),
+        store,
+    );
+
+    RunningNode {
+        rx_output,
+        worker: Some(worker),
+        _tx_feedback: Box::new(tx_feedback),
+    }
+}
+
+/// A cluster of in-process authorities, spawned by `CommitteeFixture::build`. Tests use this
+/// to submit client transactions and observe each authority's committed `Header`s, and to
+/// simulate a worker crash/restart to exercise `Helper` re-sync and recovery from `Store`.
+///
+/// The harness only has capturable task handles for the worker side (`WorkerHandle`'s
+/// `batch_maker_task`/`helper_task` — `Primary::spawn` is an external, untouched dependency that
+/// hands back nothing to hold onto), so [`Cluster::stop_worker`]/[`Cluster::restart_worker`] only
+/// stop/restart that half of a node.
+/// The primary keeps running for the lifetime of the `Cluster`; this harness cannot simulate a
+/// whole-node crash.
+pub struct Cluster {
+    committee: Committee,
+    parameters: Parameters,
+    authorities: Vec<AuthorityFixture>,
+    nodes: Vec<RunningNode>,
+}
+
+impl Cluster {
+    /// The authorities making up this cluster, in the order `CommitteeFixture::generate`
+    /// created them.
+    pub fn authorities(&self) -> &[AuthorityFixture] {
+        &self.authorities
+    }
+
+    /// The receiver of committed headers for the `i`-th authority.
+    pub fn rx_output(&mut self, i: usize) -> &mut Receiver<Header> {
+        &mut self.nodes[i].rx_output
+    }
+
+    /// A sender tests can use to feed client transactions into the `i`-th authority's worker.
+    pub fn tx_transactions(&self, i: usize) -> Sender<Vec<u8>> {
+        self.nodes[i].worker.as_ref().expect("worker is stopped").tx_transactions.clone()
+    }
+
+    /// The receiver of sealed batch digests for the `i`-th authority's worker.
+    pub fn rx_batches(&mut self, i: usize) -> &mut Receiver<Digest> {
+        &mut self.nodes[i].worker.as_mut().expect("worker is stopped").rx_batches
+    }
+
+    /// Stops the `i`-th authority's worker: aborts its `batch_maker`/`Helper` tasks and waits
+    /// for both to actually exit before returning, so a subsequent `restart_worker` is
+    /// guaranteed not to race the old tasks' `Store` handle for the same on-disk path (`abort`
+    /// only requests cancellation -- it doesn't block until the task has actually stopped
+    /// running). The rest of the committee sees this authority's batch sync requests time out.
+    /// The authority's primary keeps running (see the `Cluster` doc comment) and its on-disk
+    /// `Store` is otherwise left untouched.
+    pub async fn stop_worker(&mut self, i: usize) {
+        if let Some(worker) = self.nodes[i].worker.take() {
+            worker.batch_maker_task.abort();
+            worker.helper_task.abort();
+            let _ = worker.batch_maker_task.await;
+            let _ = worker.helper_task.await;
+        }
+    }
+
+    /// Restarts the `i`-th authority's worker, re-opening its on-disk `Store` so tests can
+    /// assert it recovers state across the restart (its new `Helper` serves batch requests from
+    /// that same store; the restarted worker does not fetch batches it missed). Stops
+    /// the old worker first (see `stop_worker`) if it is still running, so the new `Store`
+    /// handle never races the old one. Has no effect on the authority's primary, which was
+    /// never stopped.
+    pub async fn restart_worker(&mut self, i: usize) {
+        self.stop_worker(i).await;
+        let authority = &self.authorities[i];
+        self.nodes[i].worker = Some(Worker::spawn(
+            authority.keypair.name,
+            authority.network_keypair.name,
+            authority.worker_id,
+            self.committee.clone(),
+            self.parameters.clone(),
+            authority.store(),
+        ));
+    }
+}
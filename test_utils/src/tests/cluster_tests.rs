@@ -0,0 +1,49 @@
+use super::*;
+use config::Parameters;
+
+/// A submitted transaction is eventually cut into a sealed batch and its digest published.
+#[tokio::test]
+async fn worker_seals_submitted_transactions() {
+    let parameters = Parameters {
+        batch_size: 1,
+        max_batch_delay: 50,
+        ..Parameters::default()
+    };
+    let mut cluster = CommitteeFixture::generate(1)
+        .with_parameters(parameters)
+        .build();
+
+    cluster
+        .tx_transactions(0)
+        .send(b"hello".to_vec())
+        .await
+        .expect("failed to submit transaction");
+
+    let digest = cluster.rx_batches(0).recv().await;
+    assert!(digest.is_some());
+}
+
+/// Stopping and restarting a worker does not race the old worker's `Store` handle: the new one
+/// can always be opened, and the restarted worker can still seal batches.
+#[tokio::test]
+async fn worker_survives_restart() {
+    let parameters = Parameters {
+        batch_size: 1,
+        max_batch_delay: 50,
+        ..Parameters::default()
+    };
+    let mut cluster = CommitteeFixture::generate(1)
+        .with_parameters(parameters)
+        .build();
+
+    cluster.restart_worker(0).await;
+
+    cluster
+        .tx_transactions(0)
+        .send(b"hello-after-restart".to_vec())
+        .await
+        .expect("failed to submit transaction");
+
+    let digest = cluster.rx_batches(0).recv().await;
+    assert!(digest.is_some());
+}
@@ -1,86 +1,359 @@
 // Copyright(C) Facebook, Inc. and its affiliates.
 use bytes::Bytes;
-use config::{Committee, WorkerId};
+use config::{Committee, Parameters, WorkerId};
 use crypto::{Digest, PublicKey};
 use log::{debug, error, warn};
-use network::{ReliableSender, SimpleSender};
+use network::ReliableSender;
 use store::Store;
 use tokio::sync::mpsc::Receiver;
 use network::CancelHandler;
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::{Arc, RwLock};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
 
 #[cfg(test)]
 #[path = "tests/helper_tests.rs"]
 pub mod helper_tests;
 
+/// Score charged for every digest a peer asks us to serve.
+const COST_PER_DIGEST: f64 = 1.0;
+/// Extra penalty charged when the peer asks for a digest we don't hold.
+const MISSING_DIGEST_PENALTY: f64 = 5.0;
+/// Extra penalty charged when the peer re-requests a digest it already asked for recently.
+const REPEAT_REQUEST_PENALTY: f64 = 10.0;
+/// Reward (score decrease) for serving a digest the peer legitimately needed.
+const SERVE_REWARD: f64 = 0.5;
+/// How long we remember a (peer, digest) request for repeat-request detection.
+const REPEAT_REQUEST_WINDOW: Duration = Duration::from_secs(5);
+/// How long a peer that crossed the reputation threshold is kept in cooldown.
+const COOLDOWN_PERIOD: Duration = Duration::from_secs(30);
+/// Per-second exponential decay rate applied to every peer's score.
+const SCORE_DECAY_PER_SEC: f64 = 0.1;
+
+/// Maximum number of unacknowledged batches we keep retrying towards a single destination;
+/// beyond this we drop the oldest one rather than growing unbounded against a dead peer.
+const MAX_PENDING_PER_DESTINATION: usize = 100;
+/// Delay before the first retry of an unacknowledged batch.
+const INITIAL_RETRY_DELAY: Duration = Duration::from_millis(200);
+/// Upper bound on the exponential retry backoff.
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(10);
+/// Number of retries attempted before a batch is given up on and garbage-collected.
+const MAX_RETRIES: u32 = 5;
+/// How often we poll in-flight batches for acknowledgment or retry.
+const RETRY_TICK: Duration = Duration::from_millis(100);
+
+/// An unacknowledged batch, together with the bookkeeping needed to retry it.
+struct PendingBatch {
+    data: Bytes,
+    handler: CancelHandler,
+    attempts: u32,
+    next_retry_at: Instant,
+}
+
+/// Polls a cancel handler without blocking; we re-poll on our own timer so a no-op waker
+/// is enough (no need for the runtime to wake us up).
+fn poll_handler(handler: &mut CancelHandler) -> Poll<()> {
+    let waker = futures::task::noop_waker();
+    let mut cx = Context::from_waker(&waker);
+    match Pin::new(handler).poll(&mut cx) {
+        Poll::Ready(_) => Poll::Ready(()),
+        Poll::Pending => Poll::Pending,
+    }
+}
+
+/// The backoff delay before the next retry of an unacknowledged batch that has already been
+/// retried `attempts` times, doubling each time up to `MAX_RETRY_DELAY`. Pulled out of `retry_pending` (and independent of
+/// `CancelHandler`/`ReliableSender`) so it can be unit-tested without a real network.
+fn next_retry_delay(attempts: u32) -> Duration {
+    INITIAL_RETRY_DELAY
+        .saturating_mul(1 << attempts.min(16))
+        .min(MAX_RETRY_DELAY)
+}
+
+/// Whether a batch that has been retried `attempts` times should be given up on rather than
+/// retried again.
+fn should_give_up(attempts: u32) -> bool {
+    attempts >= MAX_RETRIES
+}
+
+/// Pushes `item` onto the back of `queue`, dropping the oldest entry first if it is already at
+/// `cap`. Generic (and so independent of `PendingBatch`/`CancelHandler`) purely so it can be
+/// unit-tested without a real network.
+fn push_capped<T>(queue: &mut VecDeque<T>, item: T, cap: usize) {
+    if queue.len() >= cap {
+        queue.pop_front();
+    }
+    queue.push_back(item);
+}
+
+/// Every peer's current reputation score, shared with whoever spawned this `Helper` (e.g. the
+/// admin RPC server's `admin_helperReputation` method) so it can be read without going through
+/// the `Helper`'s own request channel.
+pub type SharedReputation = Arc<RwLock<HashMap<PublicKey, f64>>>;
+
+/// Tracks per-peer reputation so that a small number of misbehaving authorities cannot
+/// monopolize the time we spend serving batch requests.
+struct Reputation {
+    /// Configurable score above which a peer is put into cooldown.
+    threshold: f64,
+    /// Current score of every peer we've heard from (higher is worse).
+    scores: HashMap<PublicKey, f64>,
+    /// Last time we touched a peer's score, used to apply time-based decay.
+    last_update: HashMap<PublicKey, Instant>,
+    /// Peers currently serving a cooldown, and when it ends.
+    cooldowns: HashMap<PublicKey, Instant>,
+    /// Recently served (or missed) `(origin, digest)` pairs, used to detect repeat requests.
+    recent_requests: HashMap<(PublicKey, Digest), Instant>,
+    /// Mirror of `scores`, published after every change so readers outside this task see it.
+    shared: SharedReputation,
+}
+
+impl Reputation {
+    fn new(threshold: f64, shared: SharedReputation) -> Self {
+        Self {
+            threshold,
+            scores: HashMap::new(),
+            last_update: HashMap::new(),
+            cooldowns: HashMap::new(),
+            recent_requests: HashMap::new(),
+            shared,
+        }
+    }
+
+    /// Publishes the current scores to `shared`.
+    fn publish(&self) {
+        *self.shared.write().expect("reputation lock poisoned") = self.scores.clone();
+    }
+
+    /// Applies exponential decay to `origin`'s score based on elapsed time since the last update.
+    fn decay(&mut self, origin: &PublicKey, now: Instant) {
+        let last = *self.last_update.entry(*origin).or_insert(now);
+        let elapsed = now.saturating_duration_since(last).as_secs_f64();
+        if let Some(score) = self.scores.get_mut(origin) {
+            *score *= (-SCORE_DECAY_PER_SEC * elapsed).exp();
+        }
+        self.last_update.insert(*origin, now);
+    }
+
+    /// Adjusts `origin`'s score by `delta` (positive charges, negative rewards) and returns
+    /// whether the peer is (now) over the cooldown threshold.
+    fn adjust(&mut self, origin: &PublicKey, delta: f64, now: Instant) -> bool {
+        self.decay(origin, now);
+        let score = self.scores.entry(*origin).or_insert(0.0);
+        *score = (*score + delta).max(0.0);
+        let over_threshold = *score > self.threshold;
+        if over_threshold {
+            self.cooldowns.insert(*origin, now + COOLDOWN_PERIOD);
+        }
+        self.publish();
+        over_threshold
+    }
+
+    /// Whether `origin` is currently serving a cooldown.
+    fn in_cooldown(&mut self, origin: &PublicKey, now: Instant) -> bool {
+        match self.cooldowns.get(origin) {
+            Some(until) if *until > now => true,
+            Some(_) => {
+                self.cooldowns.remove(origin);
+                false
+            }
+            None => false,
+        }
+    }
+
+    /// Charges `origin` for requesting `digest`, penalizing misses and repeat requests.
+    /// Returns `true` if the peer should be dropped for the rest of this batch.
+    fn charge_request(&mut self, origin: &PublicKey, digest: &Digest, have_it: bool, now: Instant) -> bool {
+        let mut cost = COST_PER_DIGEST;
+        if !have_it {
+            cost += MISSING_DIGEST_PENALTY;
+        }
+
+        let key = (*origin, digest.clone());
+        if let Some(last) = self.recent_requests.get(&key) {
+            if now.saturating_duration_since(*last) < REPEAT_REQUEST_WINDOW {
+                cost += REPEAT_REQUEST_PENALTY;
+            }
+        }
+        self.recent_requests.insert(key, now);
+        // Bound the map: forget entries that fell out of the sliding window.
+        self.recent_requests
+            .retain(|_, seen| now.saturating_duration_since(*seen) < REPEAT_REQUEST_WINDOW);
+
+        let over_threshold = self.adjust(origin, cost, now);
+        if have_it {
+            // Serving a legitimate request is cheap/beneficial: decay the cost a little.
+            self.adjust(origin, -SERVE_REWARD, now);
+        }
+        over_threshold
+    }
+}
+
 /// A task dedicated to help other authorities by replying to their batch requests.
 pub struct Helper {
     /// The id of this worker.
     id: WorkerId,
     /// The committee information.
     committee: Committee,
+    /// The node's parameters.
+    parameters: Parameters,
     /// The persistent storage.
     store: Store,
     /// Input channel to receive batch requests.
     rx_request: Receiver<(Vec<Digest>, PublicKey)>,
     /// A network sender to send the batches to the other workers.
-    network: SimpleSender,
-    //network: ReliableSender,
-    // Cancel handlers
-    cancel_handlers: Vec<CancelHandler>,
+    network: ReliableSender,
+    /// Unacknowledged batches, keyed by destination, awaiting ack or retry.
+    cancel_handlers: HashMap<SocketAddr, VecDeque<PendingBatch>>,
+    /// Per-origin reputation tracker, used to stop bad nodes from monopolizing our resources.
+    reputation: Reputation,
 }
 
 impl Helper {
+    /// Spawns the helper task and returns its `JoinHandle` so callers (e.g. `Worker`) can
+    /// track or abort it, for instance to simulate a worker crash in tests. `reputation` is
+    /// updated as peers are scored, so its owner can read it (e.g. to answer an admin RPC
+    /// query) without going through `rx_request`.
     pub fn spawn(
         id: WorkerId,
         committee: Committee,
+        parameters: Parameters,
         store: Store,
         rx_request: Receiver<(Vec<Digest>, PublicKey)>,
-    ) {
+        reputation: SharedReputation,
+    ) -> tokio::task::JoinHandle<()> {
+        let threshold = parameters.helper_reputation_threshold;
         tokio::spawn(async move {
             Self {
                 id,
                 committee,
+                parameters,
                 store,
                 rx_request,
-                network: SimpleSender::new(),
-                //network: ReliableSender::new(),
-                cancel_handlers: Vec::new(),
+                network: ReliableSender::new(),
+                cancel_handlers: HashMap::new(),
+                reputation: Reputation::new(threshold, reputation),
             }
             .run()
             .await;
-        });
+        })
     }
 
-    async fn run(&mut self) {
-        while let Some((digests, origin)) = self.rx_request.recv().await {
-            // TODO [issue #7]: Do some accounting to prevent bad nodes from monopolizing our resources.
-            debug!("Received helper batch request {:?}", digests);
-            // get the requestors address.
-            let address = match self.committee.worker(&origin, &self.id) {
-                Ok(x) => x.worker_to_worker,
-                Err(e) => {
-                    warn!("Unexpected batch request: {}", e);
-                    continue;
+    /// Sends `data` to `address`, tracking the resulting cancel handler so the batch is
+    /// retried (with capped exponential backoff) until it is acknowledged.
+    async fn send_batch(&mut self, address: SocketAddr, data: Bytes) {
+        let handler = self.network.send(address, data.clone()).await;
+        let queue = self.cancel_handlers.entry(address).or_insert_with(VecDeque::new);
+        if queue.len() >= MAX_PENDING_PER_DESTINATION {
+            debug!("Too many pending batches to {}, dropping the oldest", address);
+        }
+        push_capped(
+            queue,
+            PendingBatch {
+                data,
+                handler,
+                attempts: 0,
+                next_retry_at: Instant::now() + INITIAL_RETRY_DELAY,
+            },
+            MAX_PENDING_PER_DESTINATION,
+        );
+    }
+
+    /// Polls every in-flight batch: drops the ones that were acknowledged, and re-sends
+    /// (with backoff) those that are overdue for a retry. Gives up on a batch (and frees
+    /// its memory) after `MAX_RETRIES` attempts, so a permanently unreachable peer cannot
+    /// leak memory. Never blocks the receive loop.
+    async fn retry_pending(&mut self) {
+        let now = Instant::now();
+        let addresses: Vec<SocketAddr> = self.cancel_handlers.keys().cloned().collect();
+        for address in addresses {
+            let mut queue = self.cancel_handlers.remove(&address).unwrap_or_default();
+            let mut retained = VecDeque::new();
+            while let Some(mut pending) = queue.pop_front() {
+                match poll_handler(&mut pending.handler) {
+                    Poll::Ready(()) => continue, // Acknowledged: drop it.
+                    Poll::Pending if now < pending.next_retry_at => retained.push_back(pending),
+                    Poll::Pending if should_give_up(pending.attempts) => {
+                        warn!(
+                            "Giving up on batch to {} after {} attempts",
+                            address, pending.attempts
+                        );
+                    }
+                    Poll::Pending => {
+                        pending.attempts += 1;
+                        let delay = next_retry_delay(pending.attempts);
+                        pending.handler = self.network.send(address, pending.data.clone()).await;
+                        pending.next_retry_at = now + delay;
+                        retained.push_back(pending);
+                    }
                 }
-            };
-
-            // Reply to the request (the best we can).
-            for digest in digests {
-                match self.store.read(digest.to_vec()).await {
-                    Ok(Some(data)) => {
-                        debug!("have digest {:?} in store", digest);
-                        /*let handler = self.network.send(address, Bytes::from(data)).await;
-                        self.cancel_handlers.push(handler);*/
-                        self.network.send(address, Bytes::from(data)).await;
-                    },
-                    Ok(None) => {
-                        debug!("don't have digest {:?} in store", digest);
-                        ()
-                    },
-                    Err(e) => error!("{}", e),
+            }
+            if !retained.is_empty() {
+                self.cancel_handlers.insert(address, retained);
+            }
+        }
+    }
+
+    async fn run(&mut self) {
+        let mut retry_ticker = tokio::time::interval(RETRY_TICK);
+        loop {
+            tokio::select! {
+                request = self.rx_request.recv() => {
+                    let (digests, origin) = match request {
+                        Some(x) => x,
+                        None => break,
+                    };
+                    self.handle_request(digests, origin).await;
+                },
+                _ = retry_ticker.tick() => {
+                    self.retry_pending().await;
                 }
             }
         }
     }
+
+    async fn handle_request(&mut self, digests: Vec<Digest>, origin: PublicKey) {
+        let now = Instant::now();
+        if self.reputation.in_cooldown(&origin, now) {
+            debug!("Ignoring batch request from {} (in cooldown)", origin);
+            return;
+        }
+
+        debug!("Received helper batch request {:?}", digests);
+        // get the requestors address.
+        let address = match self.committee.worker(&origin, &self.id) {
+            Ok(x) => x.worker_to_worker,
+            Err(e) => {
+                warn!("Unexpected batch request: {}", e);
+                return;
+            }
+        };
+
+        // Reply to the request (the best we can), charging the origin's reputation
+        // score for each digest requested so no single peer can monopolize our time.
+        for digest in digests {
+            match self.store.read(digest.to_vec()).await {
+                Ok(Some(data)) => {
+                    debug!("have digest {:?} in store", digest);
+                    if self.reputation.charge_request(&origin, &digest, true, now) {
+                        warn!("Peer {} exceeded its reputation threshold", origin);
+                        break;
+                    }
+                    self.send_batch(address, Bytes::from(data)).await;
+                },
+                Ok(None) => {
+                    debug!("don't have digest {:?} in store", digest);
+                    if self.reputation.charge_request(&origin, &digest, false, now) {
+                        warn!("Peer {} exceeded its reputation threshold", origin);
+                        break;
+                    }
+                },
+                Err(e) => error!("{}", e),
+            }
+        }
+    }
 }
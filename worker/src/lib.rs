@@ -0,0 +1,148 @@
+// Copyright(C) Facebook, Inc. and its affiliates.
+use config::{Committee, Parameters, WorkerId};
+use crypto::{Digest, PublicKey};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use store::Store;
+use tokio::sync::mpsc::{channel, Receiver, Sender};
+use tokio::task::JoinHandle;
+use tokio::time::sleep;
+
+mod helper;
+pub use helper::{Helper, SharedReputation};
+
+/// The default channel capacity.
+const CHANNEL_CAPACITY: usize = 1_000;
+
+/// A single worker of an authority: runs the batch maker that turns client transactions into
+/// sealed batches, and the `Helper` that serves other authorities' batch sync requests.
+pub struct Worker {
+    name: PublicKey,
+    id: WorkerId,
+    committee: Committee,
+    parameters: Parameters,
+    store: Store,
+}
+
+/// What a spawned worker hands back to its caller: the tasks running it, a channel to feed it
+/// client transactions, the digests of the batches it seals as it cuts them, the channel its
+/// `Helper` receives batch requests on, and a live view of its `Helper`'s per-peer reputation
+/// scores.
+pub struct WorkerHandle {
+    pub batch_maker_task: JoinHandle<()>,
+    pub helper_task: JoinHandle<()>,
+    pub tx_transactions: Sender<Vec<u8>>,
+    pub rx_batches: Receiver<Digest>,
+    pub tx_request: Sender<(Vec<Digest>, PublicKey)>,
+    pub reputation: SharedReputation,
+}
+
+impl Worker {
+    pub fn spawn(
+        name: PublicKey,
+        _network_key: PublicKey,
+        id: WorkerId,
+        committee: Committee,
+        parameters: Parameters,
+        store: Store,
+    ) -> WorkerHandle {
+        let (tx_transactions, rx_transactions) = channel(CHANNEL_CAPACITY);
+        let (tx_request, rx_request) = channel(CHANNEL_CAPACITY);
+        let (tx_batches, rx_batches) = channel(CHANNEL_CAPACITY);
+
+        let worker = Self {
+            name,
+            id,
+            committee: committee.clone(),
+            parameters: parameters.clone(),
+            store: store.clone(),
+        };
+
+        let batch_maker_task = tokio::spawn(batch_maker(
+            worker.parameters.batch_size,
+            worker.parameters.max_batch_delay,
+            rx_transactions,
+            worker.store.clone(),
+            tx_batches,
+        ));
+
+        let reputation: SharedReputation = Arc::new(RwLock::new(HashMap::new()));
+        let helper_task = Helper::spawn(
+            worker.id,
+            worker.committee,
+            worker.parameters,
+            worker.store,
+            rx_request,
+            reputation.clone(),
+        );
+
+        WorkerHandle {
+            batch_maker_task,
+            helper_task,
+            tx_transactions,
+            rx_batches,
+            tx_request,
+            reputation,
+        }
+    }
+}
+
+/// Buffers client transactions until either `batch_size` bytes have accumulated or a
+/// `max_batch_delay`-millisecond timer fires (the timer restarts after every seal and every time
+/// it fires, not when the first transaction is buffered), then seals
+/// them into a batch, persists it under its digest, and publishes that digest on `tx_batches`.
+/// Broadcasting sealed batches to `worker_to_worker` peers is out of scope here (it needs the
+/// external `network` crate's reliable sender, already used by `Helper`); this only makes the
+/// transaction path actually produce and store batches instead of silently discarding input.
+async fn batch_maker(
+    batch_size: usize,
+    max_batch_delay: u64,
+    mut rx_transactions: Receiver<Vec<u8>>,
+    mut store: Store,
+    tx_batches: Sender<Digest>,
+) {
+    let mut buffer: Vec<Vec<u8>> = Vec::new();
+    let mut buffer_size = 0usize;
+    let timer = sleep(Duration::from_millis(max_batch_delay));
+    tokio::pin!(timer);
+
+    loop {
+        tokio::select! {
+            transaction = rx_transactions.recv() => {
+                let transaction = match transaction {
+                    Some(x) => x,
+                    None => break,
+                };
+                buffer_size += transaction.len();
+                buffer.push(transaction);
+                if buffer_size >= batch_size {
+                    seal_batch(&mut buffer, &mut buffer_size, &mut store, &tx_batches).await;
+                    timer.as_mut().reset(tokio::time::Instant::now() + Duration::from_millis(max_batch_delay));
+                }
+            },
+            _ = &mut timer => {
+                if !buffer.is_empty() {
+                    seal_batch(&mut buffer, &mut buffer_size, &mut store, &tx_batches).await;
+                }
+                timer.as_mut().reset(tokio::time::Instant::now() + Duration::from_millis(max_batch_delay));
+            }
+        }
+    }
+}
+
+/// Serializes the buffered transactions into one batch, hashes and persists it, publishes its
+/// digest, and resets the buffer for the next batch.
+async fn seal_batch(
+    buffer: &mut Vec<Vec<u8>>,
+    buffer_size: &mut usize,
+    store: &mut Store,
+    tx_batches: &Sender<Digest>,
+) {
+    let batch = serde_json::to_vec(&buffer).expect("failed to serialize batch");
+    let digest = Digest::hash(&batch);
+    store.write(digest.to_vec(), batch).await;
+    let _ = tx_batches.send(digest).await;
+    buffer.clear();
+    *buffer_size = 0;
+}
@@ -0,0 +1,105 @@
+use super::*;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+fn origin() -> PublicKey {
+    PublicKey([1u8; 32])
+}
+
+fn digest() -> Digest {
+    Digest([2u8; 32])
+}
+
+#[test]
+fn crossing_threshold_triggers_cooldown_that_expires() {
+    let shared: SharedReputation = Arc::new(RwLock::new(HashMap::new()));
+    let mut reputation = Reputation::new(/* threshold */ 0.5, shared);
+    let now = Instant::now();
+
+    // A single served request (cost 1.0) already exceeds the threshold.
+    let over_threshold = reputation.charge_request(&origin(), &digest(), true, now);
+    assert!(over_threshold);
+    assert!(reputation.in_cooldown(&origin(), now));
+
+    // Still cooling down just before the period elapses...
+    let almost_over = now + COOLDOWN_PERIOD - Duration::from_millis(1);
+    assert!(reputation.in_cooldown(&origin(), almost_over));
+
+    // ...but not once it has.
+    let after_cooldown = now + COOLDOWN_PERIOD + Duration::from_millis(1);
+    assert!(!reputation.in_cooldown(&origin(), after_cooldown));
+}
+
+#[test]
+fn score_decays_towards_zero_over_time() {
+    let shared: SharedReputation = Arc::new(RwLock::new(HashMap::new()));
+    let mut reputation = Reputation::new(/* threshold */ 1000.0, shared);
+    let now = Instant::now();
+
+    reputation.adjust(&origin(), 10.0, now);
+    assert_eq!(reputation.scores[&origin()], 10.0);
+
+    let later = now + Duration::from_secs(10);
+    reputation.decay(&origin(), later);
+    let decayed = reputation.scores[&origin()];
+    assert!(decayed > 0.0 && decayed < 10.0);
+}
+
+#[test]
+fn repeat_request_is_penalized_more_than_a_fresh_one() {
+    let shared: SharedReputation = Arc::new(RwLock::new(HashMap::new()));
+    let mut reputation = Reputation::new(/* threshold */ 1000.0, shared);
+    let now = Instant::now();
+
+    reputation.charge_request(&origin(), &digest(), /* have_it */ true, now);
+    let after_first = reputation.scores[&origin()];
+
+    reputation.charge_request(&origin(), &digest(), /* have_it */ true, now);
+    let after_repeat = reputation.scores[&origin()];
+
+    assert!(after_repeat - after_first >= REPEAT_REQUEST_PENALTY);
+}
+
+#[test]
+fn missing_digest_is_penalized_more_than_a_hit() {
+    let shared_hit: SharedReputation = Arc::new(RwLock::new(HashMap::new()));
+    let mut hit = Reputation::new(/* threshold */ 1000.0, shared_hit);
+    let shared_miss: SharedReputation = Arc::new(RwLock::new(HashMap::new()));
+    let mut miss = Reputation::new(/* threshold */ 1000.0, shared_miss);
+    let now = Instant::now();
+
+    hit.charge_request(&origin(), &digest(), /* have_it */ true, now);
+    miss.charge_request(&origin(), &digest(), /* have_it */ false, now);
+
+    assert!(miss.scores[&origin()] > hit.scores[&origin()]);
+}
+
+#[test]
+fn retry_delay_doubles_up_to_the_cap() {
+    assert_eq!(next_retry_delay(0), INITIAL_RETRY_DELAY);
+    assert_eq!(next_retry_delay(1), INITIAL_RETRY_DELAY * 2);
+    assert_eq!(next_retry_delay(2), INITIAL_RETRY_DELAY * 4);
+    assert_eq!(next_retry_delay(30), MAX_RETRY_DELAY);
+}
+
+#[test]
+fn gives_up_only_after_max_retries() {
+    assert!(!should_give_up(MAX_RETRIES - 1));
+    assert!(should_give_up(MAX_RETRIES));
+    assert!(should_give_up(MAX_RETRIES + 1));
+}
+
+#[test]
+fn capped_push_drops_the_oldest_once_full() {
+    let mut queue = VecDeque::new();
+    for i in 0..MAX_PENDING_PER_DESTINATION {
+        push_capped(&mut queue, i, MAX_PENDING_PER_DESTINATION);
+    }
+    assert_eq!(queue.len(), MAX_PENDING_PER_DESTINATION);
+    assert_eq!(*queue.front().unwrap(), 0);
+
+    push_capped(&mut queue, MAX_PENDING_PER_DESTINATION, MAX_PENDING_PER_DESTINATION);
+    assert_eq!(queue.len(), MAX_PENDING_PER_DESTINATION);
+    assert_eq!(*queue.front().unwrap(), 1);
+    assert_eq!(*queue.back().unwrap(), MAX_PENDING_PER_DESTINATION);
+}